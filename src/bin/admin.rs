@@ -0,0 +1,77 @@
+use std::fs;
+use std::process;
+
+use diesel::r2d2::{self, ConnectionManager};
+use structopt::StructOpt;
+
+use web::db::{self, PgPool};
+use web::models::NewPost;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "admin", about = "Manage blog posts without touching SQL directly.")]
+enum Command {
+    /// Create a new post from a Markdown file.
+    CreatePost {
+        #[structopt(long)]
+        title: String,
+        #[structopt(long)]
+        slug: String,
+        #[structopt(long, parse(from_os_str))]
+        file: std::path::PathBuf,
+    },
+    /// Unpublish a post without deleting it.
+    HidePost { slug: String },
+    /// Permanently remove a post.
+    DeletePost { slug: String },
+    /// List every post, published or not.
+    ListPosts,
+}
+
+fn build_pool() -> PgPool {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let manager = ConnectionManager::new(database_url);
+    r2d2::Pool::new(manager).expect("Failed to create connection pool")
+}
+
+fn main() {
+    let pool = build_pool();
+
+    let result = match Command::from_args() {
+        Command::CreatePost { title, slug, file } => {
+            let body = fs::read_to_string(&file).unwrap_or_else(|e| {
+                eprintln!("Could not read {}: {}", file.display(), e);
+                process::exit(1);
+            });
+            db::insert_post(
+                NewPost {
+                    title: &title,
+                    slug: &slug,
+                    body: &body,
+                    published: true,
+                },
+                &pool,
+            )
+            .map(|post| println!("Created post {} ({})", post.slug, post.id))
+        }
+        Command::HidePost { slug } => db::set_post_published(&slug, false, &pool)
+            .map(|post| println!("Hid post {}", post.slug)),
+        Command::DeletePost { slug } => {
+            db::delete_post(&slug, &pool).map(|_| println!("Deleted post {}", slug))
+        }
+        Command::ListPosts => db::select_all_posts(&pool).map(|posts| {
+            for post in posts {
+                println!(
+                    "{}\t{}\t{}",
+                    post.slug,
+                    if post.published { "published" } else { "hidden" },
+                    post.title
+                );
+            }
+        }),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
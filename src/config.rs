@@ -0,0 +1,23 @@
+use std::env;
+
+/// Runtime configuration read from the environment at startup.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Shared secret that must accompany any request to the authoring routes.
+    pub submit_token: String,
+    /// Scheme+host the site is served at (e.g. `https://example.com`), with no
+    /// trailing slash, used to build absolute permalinks like the RSS feed's.
+    pub base_url: String,
+}
+
+impl AppConfig {
+    pub fn from_env() -> Self {
+        AppConfig {
+            submit_token: env::var("SUBMIT_TOKEN").expect("SUBMIT_TOKEN must be set"),
+            base_url: env::var("BASE_URL")
+                .expect("BASE_URL must be set")
+                .trim_end_matches('/')
+                .to_string(),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use derive_more::{Display, Error, From};
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+
+use crate::models::{Comment, NewComment, NewPost, Post};
+use crate::schema::comments::dsl as comments_dsl;
+use crate::schema::posts::dsl::*;
+
+pub type PgPool = r2d2::Pool<ConnectionManager<PgConnection>>;
+
+#[derive(Debug, Display, Error, From)]
+pub enum DatabaseError {
+    #[display(fmt = "could not obtain a connection from the pool: {}", _0)]
+    ConnectionPoolError(r2d2::PoolError),
+    #[display(fmt = "record not found: {}", _0)]
+    NotFound(diesel::result::Error),
+    #[display(fmt = "query failed: {}", _0)]
+    QueryError(diesel::result::Error),
+}
+
+fn connection(pool: &PgPool) -> Result<r2d2::PooledConnection<ConnectionManager<PgConnection>>, DatabaseError> {
+    pool.get().map_err(DatabaseError::ConnectionPoolError)
+}
+
+fn map_query_error(e: diesel::result::Error) -> DatabaseError {
+    match e {
+        diesel::result::Error::NotFound => DatabaseError::NotFound(e),
+        _ => DatabaseError::QueryError(e),
+    }
+}
+
+pub fn select_last_n_posts(n: i64, pool: &PgPool) -> Result<Vec<Post>, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .filter(published.eq(true))
+        .order(created_at.desc())
+        .limit(n)
+        .load::<Post>(&conn)
+        .map_err(map_query_error)
+}
+
+pub fn select_post_with_slug(post_slug: &str, pool: &PgPool) -> Result<Post, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .filter(slug.eq(post_slug))
+        .filter(published.eq(true))
+        .first::<Post>(&conn)
+        .map_err(map_query_error)
+}
+
+/// Fetch one page of published posts, newest first, for the `/blog/all` archive.
+pub fn select_posts_page(offset: i64, limit: i64, pool: &PgPool) -> Result<Vec<Post>, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .filter(published.eq(true))
+        .order(created_at.desc())
+        .offset(offset)
+        .limit(limit)
+        .load::<Post>(&conn)
+        .map_err(map_query_error)
+}
+
+/// Total number of published posts, used to compute `total_pages` for pagination.
+pub fn count_posts(pool: &PgPool) -> Result<i64, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .filter(published.eq(true))
+        .count()
+        .get_result(&conn)
+        .map_err(map_query_error)
+}
+
+/// Fetch a post by slug regardless of its `published` state, for the
+/// authoring routes that need to load drafts and hidden posts back for editing.
+pub fn select_post_with_slug_any(post_slug: &str, pool: &PgPool) -> Result<Post, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .filter(slug.eq(post_slug))
+        .first::<Post>(&conn)
+        .map_err(map_query_error)
+}
+
+pub fn insert_post(new_post: NewPost, pool: &PgPool) -> Result<Post, DatabaseError> {
+    let conn = connection(pool)?;
+
+    diesel::insert_into(posts)
+        .values(&new_post)
+        .get_result(&conn)
+        .map_err(map_query_error)
+}
+
+pub fn update_post(post_slug: &str, new_title: &str, new_body: &str, pool: &PgPool) -> Result<Post, DatabaseError> {
+    let conn = connection(pool)?;
+
+    diesel::update(posts.filter(slug.eq(post_slug)))
+        .set((title.eq(new_title), body.eq(new_body)))
+        .get_result(&conn)
+        .map_err(map_query_error)
+}
+
+/// Toggle whether a post is listed on `blog()`/served by `post()`.
+pub fn set_post_published(post_slug: &str, is_published: bool, pool: &PgPool) -> Result<Post, DatabaseError> {
+    let conn = connection(pool)?;
+
+    diesel::update(posts.filter(slug.eq(post_slug)))
+        .set(published.eq(is_published))
+        .get_result(&conn)
+        .map_err(map_query_error)
+}
+
+pub fn select_comments_for_post(post_id: i32, pool: &PgPool) -> Result<Vec<Comment>, DatabaseError> {
+    let conn = connection(pool)?;
+
+    comments_dsl::comments
+        .filter(comments_dsl::post_id.eq(post_id))
+        .order(comments_dsl::created_at.asc())
+        .load::<Comment>(&conn)
+        .map_err(map_query_error)
+}
+
+/// Permanently remove a post, for the admin CLI's `delete-post` command.
+pub fn delete_post(post_slug: &str, pool: &PgPool) -> Result<(), DatabaseError> {
+    let conn = connection(pool)?;
+
+    diesel::delete(posts.filter(slug.eq(post_slug)))
+        .execute(&conn)
+        .map_err(map_query_error)?;
+
+    Ok(())
+}
+
+/// All posts regardless of `published` state, for the admin CLI's `list-posts`.
+pub fn select_all_posts(pool: &PgPool) -> Result<Vec<Post>, DatabaseError> {
+    let conn = connection(pool)?;
+
+    posts
+        .order(created_at.desc())
+        .load::<Post>(&conn)
+        .map_err(map_query_error)
+}
+
+/// Look up the parent post by slug and insert a comment under it, so a
+/// missing post surfaces the same `NotFound` the post route uses.
+pub fn insert_comment(post_slug: &str, author: &str, comment_body: &str, pool: &PgPool) -> Result<Comment, DatabaseError> {
+    let conn = connection(pool)?;
+
+    let parent = posts
+        .filter(slug.eq(post_slug))
+        .filter(published.eq(true))
+        .first::<Post>(&conn)
+        .map_err(map_query_error)?;
+
+    diesel::insert_into(comments_dsl::comments)
+        .values(&NewComment {
+            post_id: parent.id,
+            author,
+            body: comment_body,
+        })
+        .get_result(&conn)
+        .map_err(map_query_error)
+}
@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate diesel;
+
+pub mod config;
+pub mod db;
+pub mod models;
+pub mod render;
+pub mod routes;
+pub mod schema;
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::schema::{comments, posts};
+
+#[derive(Debug, Clone, Queryable, Serialize)]
+pub struct Post {
+    pub id: i32,
+    pub title: String,
+    pub slug: String,
+    pub body: String,
+    pub published: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "posts"]
+pub struct NewPost<'a> {
+    pub title: &'a str,
+    pub slug: &'a str,
+    pub body: &'a str,
+    pub published: bool,
+}
+
+#[derive(Debug, Clone, Queryable, Serialize)]
+pub struct Comment {
+    pub id: i32,
+    pub post_id: i32,
+    pub author: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "comments"]
+pub struct NewComment<'a> {
+    pub post_id: i32,
+    pub author: &'a str,
+    pub body: &'a str,
+}
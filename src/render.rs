@@ -0,0 +1,77 @@
+use pulldown_cmark::{html, Options, Parser};
+use serde::Serialize;
+
+use crate::models::Post;
+
+/// Convert Markdown post bodies to sanitized HTML, stripping anything that
+/// could carry stored XSS before it ever reaches a template.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::all());
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::clean(&unsafe_html)
+}
+
+/// Character budget for `PostView::body_excerpt`, before the closing ellipsis.
+const EXCERPT_CHARS: usize = 280;
+
+/// Truncate already-sanitized HTML to roughly `EXCERPT_CHARS` characters and
+/// re-sanitize, which closes any tag left dangling by the cut.
+fn excerpt_of(body_html: &str) -> String {
+    let truncated: String = body_html.chars().take(EXCERPT_CHARS).collect();
+    if truncated.chars().count() == body_html.chars().count() {
+        return body_html.to_string();
+    }
+
+    format!("{}…", ammonia::clean(&truncated))
+}
+
+/// A post plus its pre-rendered Markdown, for templates that display content
+/// (`blog.html.tera`, `post.html.tera`). Keeps the raw `body` around for the
+/// editing forms, which need Markdown source rather than HTML.
+#[derive(Debug, Clone, Serialize)]
+pub struct PostView {
+    #[serde(flatten)]
+    pub post: Post,
+    pub body_html: String,
+    pub body_excerpt: String,
+}
+
+impl From<Post> for PostView {
+    fn from(post: Post) -> Self {
+        let body_html = markdown_to_html(&post.body);
+        let body_excerpt = excerpt_of(&body_html);
+        PostView { post, body_html, body_excerpt }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_to_html_renders_basic_markdown() {
+        assert_eq!(markdown_to_html("# Hi\n\nThere."), "<h1>Hi</h1>\n<p>There.</p>\n");
+    }
+
+    #[test]
+    fn markdown_to_html_strips_script_tags() {
+        let html = markdown_to_html("<script>alert('xss')</script>\n\nHello");
+        assert!(!html.contains("<script"));
+    }
+
+    #[test]
+    fn excerpt_of_passes_through_short_html_unchanged() {
+        let html = "<p>short</p>";
+        assert_eq!(excerpt_of(html), html);
+    }
+
+    #[test]
+    fn excerpt_of_truncates_long_html_with_ellipsis() {
+        let html = format!("<p>{}</p>", "a".repeat(EXCERPT_CHARS + 50));
+        let excerpt = excerpt_of(&html);
+        assert!(excerpt.ends_with('…'));
+        assert!(excerpt.len() < html.len());
+    }
+}
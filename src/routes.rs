@@ -1,11 +1,24 @@
 use log;
 
-use actix_web::{error, error::BlockingError, web, HttpResponse, Result, dev::HttpResponseBuilder, http::header, http::StatusCode};
+use actix_web::{error, error::BlockingError, web, HttpRequest, HttpResponse, Result, dev::HttpResponseBuilder, http::header, http::StatusCode};
 use derive_more::{Display, Error};
+use once_cell::sync::OnceCell;
+use subtle::ConstantTimeEq;
 use tera::{Context, Tera};
 
+use crate::config::AppConfig;
 use crate::db::{self, DatabaseError};
+use crate::models::NewPost;
+use crate::render::PostView;
 
+/// Set once at startup from the same `Tera` instance registered as app data,
+/// so `ServerError::error_response` (which only gets `&self`) can still
+/// render a themed error page instead of a bare string.
+static ERROR_TEMPLATES: OnceCell<Tera> = OnceCell::new();
+
+pub fn set_error_templates(tmpl: Tera) {
+    let _ = ERROR_TEMPLATES.set(tmpl);
+}
 
 #[derive(Debug, Display, Error)]
 pub enum ServerError {
@@ -13,24 +26,66 @@ pub enum ServerError {
     InternalError,
     #[display(fmt = "The post you are looking for does not exist.")]
     PostNotFound,
+    #[display(fmt = "You are not authorized to perform this action.")]
+    Unauthorized,
+    #[display(fmt = "{}", _0)]
+    BadRequest(#[error(not(source))] &'static str),
 }
 
 
 impl error::ResponseError for ServerError {
     fn error_response(&self) -> HttpResponse {
+        let body = ERROR_TEMPLATES
+            .get()
+            .and_then(|tmpl| render_error(tmpl, self).ok())
+            .unwrap_or_else(|| self.to_string());
+
         HttpResponseBuilder::new(self.status_code())
             .set_header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-            .body(self.to_string())
+            .body(body)
     }
 
     fn status_code(&self) -> StatusCode {
         match *self {
             ServerError::PostNotFound => StatusCode::NOT_FOUND,
             ServerError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ServerError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ServerError::BadRequest(_) => StatusCode::BAD_REQUEST,
         }
     }
 }
 
+/// Render `error.html.tera` for `err`. On render failure the error is returned
+/// and `error_response` falls back to the plain `Display` string, so a broken
+/// template can't trigger an error loop.
+fn render_error(tmpl: &Tera, err: &ServerError) -> tera::Result<String> {
+    let status = err.status_code();
+
+    let mut context = Context::new();
+    context.insert("status", &status.as_u16());
+    context.insert("message", &err.to_string());
+    context.insert("is_not_found", &matches!(err, ServerError::PostNotFound));
+    context.insert("is_client_error", &status.is_client_error());
+
+    tmpl.render("error.html.tera", &context)
+}
+
+/// Guard for the authoring routes: accepts either a `Bearer` `Authorization`
+/// header or a `submit_token` cookie matching the configured submit token.
+fn require_submit_token(req: &HttpRequest, config: &AppConfig) -> Result<(), ServerError> {
+    let provided = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_start_matches("Bearer ").to_string())
+        .or_else(|| req.cookie("submit_token").map(|c| c.value().to_string()));
+
+    match provided {
+        Some(token) if token.as_bytes().ct_eq(config.submit_token.as_bytes()).into() => Ok(()),
+        _ => Err(ServerError::Unauthorized),
+    }
+}
+
 pub async fn index(tmpl: web::Data<Tera>) -> Result<HttpResponse, ServerError> {
     let context = Context::new();
     let rendered = tmpl
@@ -62,7 +117,72 @@ pub async fn blog(
                 }
             }
         })?;
+    let posts: Vec<PostView> = posts.into_iter().map(PostView::from).collect();
+    context.insert("posts", &posts);
+
+    let rendered = tmpl
+        .render("blog.html.tera", &context)
+        .map_err(|e| {
+            log::error!("Failed to render template: {}", e);
+            ServerError::InternalError
+        })?;
+
+    Ok(HttpResponse::Ok().body(rendered))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct BlogPageQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+/// Upper bound on `per_page`, so a client can't force an unbounded scan.
+const MAX_PER_PAGE: i64 = 100;
+/// Upper bound on `page`, chosen so `page * MAX_PER_PAGE` can never overflow `i64`.
+const MAX_PAGE: i64 = i64::MAX / MAX_PER_PAGE;
+
+/// Clamp raw `page`/`per_page` query params into a range that can't overflow
+/// `page * per_page` and can't force an unbounded `OFFSET`/`LIMIT` scan.
+fn clamp_page_params(page: Option<i64>, per_page: Option<i64>) -> (i64, i64) {
+    (
+        page.unwrap_or(0).clamp(0, MAX_PAGE),
+        per_page.unwrap_or(10).clamp(1, MAX_PER_PAGE),
+    )
+}
+
+/// Full archive of published posts under `/blog/all`, with `page`/`per_page`
+/// query params defaulting to page 0, 10 per page.
+pub async fn blog_paged(
+    pool: web::Data<db::PgPool>,
+    tmpl: web::Data<Tera>,
+    query: web::Query<BlogPageQuery>,
+) -> Result<HttpResponse, ServerError> {
+    let (page, per_page) = clamp_page_params(query.page, query.per_page);
+
+    let mut context = Context::new();
+
+    let count_pool = pool.clone();
+    let total_posts = web::block(move || db::count_posts(&count_pool))
+        .await
+        .map_err(|e| {
+            log::error!("Database error: {}", e);
+            ServerError::InternalError
+        })?;
+    let total_pages = ((total_posts - 1).max(0) / per_page) + 1;
+
+    let posts = web::block(move || db::select_posts_page(page * per_page, per_page, &pool))
+        .await
+        .map_err(|e| {
+            log::error!("Database error: {}", e);
+            ServerError::InternalError
+        })?;
+
+    let posts: Vec<PostView> = posts.into_iter().map(PostView::from).collect();
     context.insert("posts", &posts);
+    context.insert("page", &page);
+    context.insert("total_pages", &total_pages);
+    context.insert("has_prev", &(page > 0));
+    context.insert("has_next", &(page + 1 < total_pages));
 
     let rendered = tmpl
         .render("blog.html.tera", &context)
@@ -98,7 +218,19 @@ pub async fn post(
                 }
             }
         })?;
+
+    let comment_pool = pool.clone();
+    let post_id = post.id;
+    let comments = web::block(move || db::select_comments_for_post(post_id, &comment_pool))
+        .await
+        .map_err(|e| {
+            log::error!("Database error: {}", e);
+            ServerError::InternalError
+        })?;
+
+    let post = PostView::from(post);
     context.insert("post", &post);
+    context.insert("comments", &comments);
 
     let rendered = tmpl
         .render("post.html.tera", &context)
@@ -110,6 +242,107 @@ pub async fn post(
     Ok(HttpResponse::Ok().body(rendered))
 }
 
+const MAX_COMMENT_AUTHOR_LEN: usize = 80;
+const MAX_COMMENT_BODY_LEN: usize = 2000;
+
+/// Escape the five HTML-significant characters so a stored comment can never
+/// inject markup, even if a template later renders it with `safe`.
+fn escape_html(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct CommentForm {
+    author: String,
+    body: String,
+}
+
+pub async fn add_comment(
+    pool: web::Data<db::PgPool>,
+    post_slug: web::Path<String>,
+    form: web::Form<CommentForm>,
+) -> Result<HttpResponse, ServerError> {
+    let author = form.author.trim();
+    let body = form.body.trim();
+
+    if author.is_empty() || author.chars().count() > MAX_COMMENT_AUTHOR_LEN {
+        return Err(ServerError::BadRequest("Name must be between 1 and 80 characters."));
+    }
+    if body.is_empty() || body.chars().count() > MAX_COMMENT_BODY_LEN {
+        return Err(ServerError::BadRequest("Comment must be between 1 and 2000 characters."));
+    }
+
+    let author = escape_html(author);
+    let body = escape_html(body);
+    let slug = post_slug.into_inner();
+    let redirect_slug = slug.clone();
+
+    web::block(move || db::insert_comment(&slug, &author, &body, &pool))
+        .await
+        .map_err(|e| {
+            match e {
+                BlockingError::Error(DatabaseError::NotFound(_)) => {
+                    log::error!("Post not found: {}", e);
+                    ServerError::PostNotFound
+                },
+                _ => {
+                    log::error!("Database error: {}", e);
+                    ServerError::InternalError
+                }
+            }
+        })?;
+
+    Ok(HttpResponse::Found()
+        .set_header(header::LOCATION, format!("/post/{}", redirect_slug))
+        .finish())
+}
+
+/// Serve the last 20 published posts as an RSS 2.0 feed at `/blog/feed.xml`.
+pub async fn feed(pool: web::Data<db::PgPool>, config: web::Data<AppConfig>) -> Result<HttpResponse, ServerError> {
+    let posts = web::block(move || db::select_last_n_posts(20, &pool))
+        .await
+        .map_err(|e| {
+            match e {
+                BlockingError::Error(DatabaseError::ConnectionPoolError(_)) => {
+                    log::error!("Error with connection pool: {}", e);
+                    ServerError::InternalError
+                },
+                _ => {
+                    log::error!("Database error: {}", e);
+                    ServerError::InternalError
+                }
+            }
+        })?;
+
+    let items: String = posts
+        .iter()
+        .map(|post| {
+            format!(
+                "  <item>\n    <title>{title}</title>\n    <link>{link}</link>\n    <guid isPermaLink=\"true\">{link}</guid>\n    <pubDate>{pub_date}</pubDate>\n    <description>{description}</description>\n  </item>\n",
+                title = escape_html(&post.title),
+                link = format!("{}/post/{}", config.base_url, post.slug),
+                pub_date = post.created_at.to_rfc2822(),
+                description = escape_html(&post.body),
+            )
+        })
+        .collect();
+
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n  <title>Blog</title>\n  <link>{base_url}/blog</link>\n  <description>Latest posts</description>\n{items}</channel>\n</rss>\n",
+        base_url = config.base_url,
+        items = items,
+    );
+
+    Ok(HttpResponse::Ok()
+        .set_header(header::CONTENT_TYPE, "application/rss+xml; charset=utf-8")
+        .body(body))
+}
+
 pub async fn hire_me(tmpl: web::Data<Tera>) -> Result<HttpResponse, ServerError> {
     let context = Context::new();
 
@@ -122,3 +355,288 @@ pub async fn hire_me(tmpl: web::Data<Tera>) -> Result<HttpResponse, ServerError>
 
     Ok(HttpResponse::Ok().body(rendered))
 }
+
+pub async fn submit_form(tmpl: web::Data<Tera>, config: web::Data<AppConfig>, req: HttpRequest) -> Result<HttpResponse, ServerError> {
+    require_submit_token(&req, &config)?;
+
+    let context = Context::new();
+    let rendered = tmpl
+        .render("submit.html.tera", &context)
+        .map_err(|e| {
+            log::error!("Failed to render template: {}", e);
+            ServerError::InternalError
+        })?;
+
+    Ok(HttpResponse::Ok().body(rendered))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PostForm {
+    title: String,
+    slug: String,
+    body: String,
+}
+
+/// Slugs created through `submit` become URL path segments, so only allow the
+/// characters a permalink can safely contain.
+fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= 200
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-')
+}
+
+pub async fn submit(
+    pool: web::Data<db::PgPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    form: web::Form<PostForm>,
+) -> Result<HttpResponse, ServerError> {
+    require_submit_token(&req, &config)?;
+
+    if !is_valid_slug(&form.slug) {
+        return Err(ServerError::BadRequest(
+            "Slug must be lowercase letters, digits, and hyphens only.",
+        ));
+    }
+
+    let slug = form.slug.clone();
+    web::block(move || {
+        db::insert_post(
+            NewPost {
+                title: &form.title,
+                slug: &form.slug,
+                body: &form.body,
+                published: true,
+            },
+            &pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("Database error: {}", e);
+        ServerError::InternalError
+    })?;
+
+    Ok(HttpResponse::Found()
+        .set_header(header::LOCATION, format!("/post/{}", slug))
+        .finish())
+}
+
+pub async fn edit_form(
+    pool: web::Data<db::PgPool>,
+    tmpl: web::Data<Tera>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    post_slug: web::Path<String>,
+) -> Result<HttpResponse, ServerError> {
+    require_submit_token(&req, &config)?;
+
+    let mut context = Context::new();
+    let post = web::block(move || db::select_post_with_slug_any(&post_slug, &pool))
+        .await
+        .map_err(|e| {
+            match e {
+                BlockingError::Error(DatabaseError::NotFound(_)) => {
+                    log::error!("Post not found: {}", e);
+                    ServerError::PostNotFound
+                },
+                _ => {
+                    log::error!("Database error: {}", e);
+                    ServerError::InternalError
+                }
+            }
+        })?;
+    context.insert("post", &post);
+
+    let rendered = tmpl
+        .render("edit.html.tera", &context)
+        .map_err(|e| {
+            log::error!("Failed to render template: {}", e);
+            ServerError::InternalError
+        })?;
+
+    Ok(HttpResponse::Ok().body(rendered))
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct EditForm {
+    title: String,
+    body: String,
+}
+
+pub async fn edit(
+    pool: web::Data<db::PgPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    post_slug: web::Path<String>,
+    form: web::Form<EditForm>,
+) -> Result<HttpResponse, ServerError> {
+    require_submit_token(&req, &config)?;
+
+    let slug = post_slug.into_inner();
+    let redirect_slug = slug.clone();
+    web::block(move || db::update_post(&slug, &form.title, &form.body, &pool))
+        .await
+        .map_err(|e| {
+            match e {
+                BlockingError::Error(DatabaseError::NotFound(_)) => {
+                    log::error!("Post not found: {}", e);
+                    ServerError::PostNotFound
+                },
+                _ => {
+                    log::error!("Database error: {}", e);
+                    ServerError::InternalError
+                }
+            }
+        })?;
+
+    Ok(HttpResponse::Found()
+        .set_header(header::LOCATION, format!("/edit/{}", redirect_slug))
+        .finish())
+}
+
+async fn set_published(
+    pool: web::Data<db::PgPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    post_slug: web::Path<String>,
+    is_published: bool,
+) -> Result<HttpResponse, ServerError> {
+    require_submit_token(&req, &config)?;
+
+    let slug = post_slug.into_inner();
+    let redirect_slug = slug.clone();
+    web::block(move || db::set_post_published(&slug, is_published, &pool))
+        .await
+        .map_err(|e| {
+            match e {
+                BlockingError::Error(DatabaseError::NotFound(_)) => {
+                    log::error!("Post not found: {}", e);
+                    ServerError::PostNotFound
+                },
+                _ => {
+                    log::error!("Database error: {}", e);
+                    ServerError::InternalError
+                }
+            }
+        })?;
+
+    Ok(HttpResponse::Found()
+        .set_header(header::LOCATION, format!("/edit/{}", redirect_slug))
+        .finish())
+}
+
+pub async fn hide_post(
+    pool: web::Data<db::PgPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    post_slug: web::Path<String>,
+) -> Result<HttpResponse, ServerError> {
+    set_published(pool, config, req, post_slug, false).await
+}
+
+pub async fn unhide_post(
+    pool: web::Data<db::PgPool>,
+    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    post_slug: web::Path<String>,
+) -> Result<HttpResponse, ServerError> {
+    set_published(pool, config, req, post_slug, true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamp_page_params_defaults() {
+        assert_eq!(clamp_page_params(None, None), (0, 10));
+    }
+
+    #[test]
+    fn clamp_page_params_rejects_negative_page() {
+        assert_eq!(clamp_page_params(Some(-5), Some(10)), (0, 10));
+    }
+
+    #[test]
+    fn clamp_page_params_caps_per_page() {
+        assert_eq!(clamp_page_params(Some(0), Some(100_000_000)), (0, MAX_PER_PAGE));
+    }
+
+    #[test]
+    fn clamp_page_params_caps_page_without_overflow() {
+        let (page, per_page) = clamp_page_params(Some(i64::MAX), Some(10));
+        assert_eq!(per_page, 10);
+        // Must not panic/overflow when multiplied by per_page.
+        let _offset = page * per_page;
+    }
+
+    #[test]
+    fn is_valid_slug_accepts_lowercase_alnum_and_hyphens() {
+        assert!(is_valid_slug("my-first-post"));
+        assert!(is_valid_slug("post-2"));
+    }
+
+    #[test]
+    fn is_valid_slug_rejects_bad_input() {
+        assert!(!is_valid_slug(""));
+        assert!(!is_valid_slug("Has-Uppercase"));
+        assert!(!is_valid_slug("has/slash"));
+        assert!(!is_valid_slug("-leading-hyphen"));
+        assert!(!is_valid_slug("trailing-hyphen-"));
+    }
+
+    #[test]
+    fn require_submit_token_accepts_matching_bearer_token() {
+        let config = AppConfig {
+            submit_token: "s3cret".to_string(),
+            base_url: "https://example.com".to_string(),
+        };
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+            .to_http_request();
+
+        assert!(require_submit_token(&req, &config).is_ok());
+    }
+
+    #[test]
+    fn require_submit_token_rejects_wrong_token() {
+        let config = AppConfig {
+            submit_token: "s3cret".to_string(),
+            base_url: "https://example.com".to_string(),
+        };
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer wrong"))
+            .to_http_request();
+
+        assert!(require_submit_token(&req, &config).is_err());
+    }
+
+    #[test]
+    fn require_submit_token_rejects_missing_token() {
+        let config = AppConfig {
+            submit_token: "s3cret".to_string(),
+            base_url: "https://example.com".to_string(),
+        };
+        let req = actix_web::test::TestRequest::default().to_http_request();
+
+        assert!(require_submit_token(&req, &config).is_err());
+    }
+
+    #[test]
+    fn escape_html_escapes_all_five_entities() {
+        assert_eq!(
+            escape_html(r#"<script>&"'>"#),
+            "&lt;script&gt;&amp;&quot;&#39;&gt;"
+        );
+    }
+
+    #[test]
+    fn escape_html_leaves_plain_text_untouched() {
+        assert_eq!(escape_html("just a normal comment"), "just a normal comment");
+    }
+}
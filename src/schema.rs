@@ -0,0 +1,24 @@
+table! {
+    posts (id) {
+        id -> Int4,
+        title -> Varchar,
+        slug -> Varchar,
+        body -> Text,
+        published -> Bool,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    comments (id) {
+        id -> Int4,
+        post_id -> Int4,
+        author -> Varchar,
+        body -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+joinable!(comments -> posts (post_id));
+allow_tables_to_appear_in_same_query!(comments, posts);